@@ -0,0 +1,580 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! Bitcoin blocks.
+//!
+//! A block is a bundle of transactions with a proof-of-work attached, which commits to an
+//! earlier block to form the blockchain. This module describes structures and functions needed
+//! to describe these blocks and the blockchain.
+//!
+
+use core::fmt;
+
+use hashes::Hash;
+
+use crate::blockdata::transaction::Transaction;
+use crate::consensus::encode::{self, Decodable, Encodable};
+use crate::consensus::Params;
+use crate::io::{BufRead, Write};
+use crate::pow::{CompactTarget, Target};
+use crate::{BlockHash, TxMerkleNode};
+
+/// The block version, used to signal soft-fork deployments via BIP9 versionbits (see
+/// [`crate::consensus::deployments`]).
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
+pub struct Version(i32);
+
+impl Version {
+    /// The original, pre-BIP9 block version.
+    pub const ONE: Self = Version(1);
+    /// BIP34-style version used while BIP9 was being rolled out.
+    pub const TWO: Self = Version(2);
+    /// The minimum version that signals support for BIP9 versionbits at all.
+    pub const NO_SOFT_FORK_SIGNALLING: Self = Version(Self::USE_VERSION_BITS);
+
+    const USE_VERSION_BITS: i32 = 0x2000_0000;
+
+    /// Creates a `Version` from a consensus-encoded `i32`.
+    pub fn from_consensus(v: i32) -> Self { Version(v) }
+
+    /// Returns the consensus-encoded `i32` representation of this version.
+    pub fn to_consensus(self) -> i32 { self.0 }
+}
+
+impl fmt::Debug for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "Version({:#x})", self.0) }
+}
+
+impl Encodable for Version {
+    fn consensus_encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, crate::io::Error> {
+        self.0.consensus_encode(w)
+    }
+}
+
+impl Decodable for Version {
+    fn consensus_decode<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, encode::Error> {
+        i32::consensus_decode(r).map(Version)
+    }
+}
+
+/// Bitcoin block header.
+///
+/// Contains all the block's information except the actual transactions, but including a commit
+/// to all the transactions via a Merkle root. For merged-mined chains, `aux_data` additionally
+/// carries the [`AuxPow`] proof that ties this header to a proof-of-work solved on the parent
+/// chain instead of this one; see [`Header::check_aux_pow`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Header {
+    /// Block version, now repurposed for soft-fork signalling.
+    pub version: Version,
+    /// Reference to the previous block in the chain.
+    pub prev_blockhash: BlockHash,
+    /// The root hash of the Merkle tree of transactions in this block.
+    pub merkle_root: TxMerkleNode,
+    /// The timestamp of the block, as claimed by the miner.
+    pub time: u32,
+    /// The target value below which the block hash must lie.
+    pub bits: CompactTarget,
+    /// The nonce, selected to obtain a low enough block hash.
+    pub nonce: u32,
+    /// The merged-mining proof tying this header to a parent-chain block, if this chain is
+    /// merge-mined and this block was produced under AuxPoW rules.
+    pub aux_data: Option<AuxPow>,
+}
+
+impl Header {
+    /// Returns the block hash.
+    ///
+    /// This is always computed over the "pure" header fields and does not commit to `aux_data` -
+    /// the AuxPoW proof commits back to this hash rather than the other way around.
+    pub fn block_hash(&self) -> BlockHash {
+        let mut encoder = BlockHash::engine();
+        self.version.consensus_encode(&mut encoder).expect("engines don't error");
+        self.prev_blockhash.consensus_encode(&mut encoder).expect("engines don't error");
+        self.merkle_root.consensus_encode(&mut encoder).expect("engines don't error");
+        self.time.consensus_encode(&mut encoder).expect("engines don't error");
+        self.bits.consensus_encode(&mut encoder).expect("engines don't error");
+        self.nonce.consensus_encode(&mut encoder).expect("engines don't error");
+        BlockHash::from_engine(encoder)
+    }
+
+    /// Returns the hash that the chain's proof-of-work function must meet the target with.
+    ///
+    /// This is currently just `block_hash` (double-SHA256). There is no `Params`-level hook to
+    /// swap in a different hashing algorithm yet, so a chain that hashes headers some other way
+    /// (e.g. scrypt, as many merge-mined altcoins do for their own chain) would have to override
+    /// this method directly rather than configure it.
+    pub fn pow_hash(&self) -> BlockHash { self.block_hash() }
+
+    /// Returns the total work represented by this header's target.
+    pub fn work(&self) -> crate::pow::Work { crate::pow::Work::from_target(self.target()) }
+
+    /// Returns the target this header's `bits` field decodes to.
+    pub fn target(&self) -> Target { Target::from_compact(self.bits) }
+
+    /// Checks this header's embedded [`AuxPow`] against `params`, at `height`.
+    ///
+    /// Below `params.aux_pow_start_height` this is a no-op: pre-AuxPoW blocks keep validating
+    /// with the plain PoW path (see [`crate::consensus::check_proof_of_work`]) and are not
+    /// required to (and should not) carry `aux_data`. At or above that height, `aux_data` must be
+    /// present and must check out.
+    ///
+    /// `chain_id` identifies this chain within the merged-mining commitment tree: the blockchain
+    /// Merkle branch's leaf index is expected to fall at the slot `chain_id` maps to, so a proof
+    /// solved for a different merge-mined chain sharing the same parent cannot be replayed here.
+    pub fn check_aux_pow(&self, height: u32, chain_id: u32, params: &Params) -> Result<(), AuxPowError> {
+        if !params.requires_aux_pow(height) {
+            return Ok(());
+        }
+        let Some(ref aux_pow) = self.aux_data else { return Err(AuxPowError::Missing) };
+        aux_pow.check(self.block_hash(), chain_id, self.target())
+    }
+}
+
+/// Bitcoin block.
+///
+/// A collection of transactions with a proof-of-work header committing to them all.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Block {
+    /// The block header.
+    pub header: Header,
+    /// List of transactions contained in the block.
+    pub txdata: Vec<Transaction>,
+}
+
+impl Block {
+    /// Returns the block hash.
+    pub fn block_hash(&self) -> BlockHash { self.header.block_hash() }
+}
+
+/// A Merkle branch proof: the sibling hashes needed to recompute a root, together with the
+/// index of the leaf being proven (which determines, at each level, whether the running hash is
+/// combined as the left or right child).
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct MerkleBranch<H> {
+    /// Sibling hashes, ordered from the leaf's level up to (but not including) the root.
+    pub hashes: Vec<H>,
+    /// Index of the proven leaf within its level, used to determine concatenation order at each
+    /// step of the branch.
+    pub index: u32,
+}
+
+impl<H: Hash> MerkleBranch<H> {
+    /// Recomputes the Merkle root that `leaf` proves into, by combining `leaf` with each sibling
+    /// hash in turn, using `self.index`'s bits to choose left/right concatenation order at each
+    /// level.
+    fn apply(&self, leaf: H) -> H {
+        let mut current: H = leaf;
+        for (level, sibling) in self.hashes.iter().enumerate() {
+            let current_bytes = current.as_ref().to_vec();
+            let sibling_bytes = sibling.as_ref().to_vec();
+            let mut engine = H::engine();
+            if (self.index >> level) & 1 == 1 {
+                engine.input(&sibling_bytes);
+                engine.input(&current_bytes);
+            } else {
+                engine.input(&current_bytes);
+                engine.input(&sibling_bytes);
+            }
+            current = H::from_engine(engine);
+        }
+        current
+    }
+}
+
+impl<H: Encodable> Encodable for MerkleBranch<H> {
+    fn consensus_encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, crate::io::Error> {
+        let mut len = self.hashes.consensus_encode(w)?;
+        len += self.index.consensus_encode(w)?;
+        Ok(len)
+    }
+}
+
+impl<H: Decodable> Decodable for MerkleBranch<H> {
+    fn consensus_decode<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, encode::Error> {
+        Ok(MerkleBranch { hashes: Decodable::consensus_decode(r)?, index: Decodable::consensus_decode(r)? })
+    }
+}
+
+/// The merged-mining ("AuxPoW") proof embedded in a merge-mined chain's block header.
+///
+/// It ties this chain's block hash to a proof-of-work solved on a parent chain by having the
+/// parent's coinbase transaction commit to this block's hash (via the magic-prefixed
+/// merged-mining commitment in its scriptSig, located with `blockchain_branch`), and by having
+/// that coinbase transaction itself be part of the parent block (proven with `coinbase_branch`).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AuxPow {
+    /// The parent chain's coinbase transaction, whose scriptSig commits to this block's hash.
+    pub coinbase_tx: Transaction,
+    /// Merkle branch linking `coinbase_tx` to the parent header's `merkle_root`.
+    pub coinbase_branch: MerkleBranch<TxMerkleNode>,
+    /// Merkle branch linking this block's hash into the merged-mining commitment tree embedded
+    /// in the parent coinbase, for merge-mining setups that aggregate several chains.
+    pub blockchain_branch: MerkleBranch<BlockHash>,
+    /// The parent chain's block header, whose hash must meet *this* chain's target - `bits` is
+    /// only used to compute that hash, never as the target to check it against, since it is
+    /// otherwise attacker-controlled data with no independent verification.
+    pub parent_header: Box<Header>,
+}
+
+/// The magic bytes that precede a merged-mining commitment in a parent coinbase's scriptSig.
+pub const MERGED_MINING_HEADER: [u8; 4] = [0xfa, 0xbe, 0x6d, 0x6d];
+
+/// Errors that can occur while validating an [`AuxPow`] proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuxPowError {
+    /// The header has no `aux_data` but merged-mining rules require one at this height.
+    Missing,
+    /// The parent coinbase's scriptSig does not contain the merged-mining magic at all.
+    MissingMagic,
+    /// The parent coinbase's scriptSig contains the merged-mining magic more than once, making
+    /// the commitment's position ambiguous.
+    MultipleMagic,
+    /// The blockchain Merkle branch, applied to this block's hash, does not land on the
+    /// commitment found at `MERGED_MINING_HEADER`'s expected position in the parent coinbase.
+    CommitmentMismatch,
+    /// The merged-mining commitment's claimed tree size is not a power of two matching the
+    /// blockchain Merkle branch's height.
+    ChainMerkleSizeMismatch,
+    /// The blockchain Merkle branch's leaf index is not the slot `chain_id` is assigned within
+    /// the merged-mining commitment tree, so this proof cannot be credited to this chain.
+    ChainMerkleIndexMismatch,
+    /// The coinbase Merkle branch does not hash up to the parent header's `merkle_root`.
+    CoinbaseBranchMismatch,
+    /// The parent header does not meet this chain's required target.
+    ParentPowInvalid,
+}
+
+impl fmt::Display for AuxPowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuxPowError::Missing => write!(f, "block requires an AuxPoW but has none"),
+            AuxPowError::MissingMagic =>
+                write!(f, "parent coinbase scriptSig does not contain the merged-mining magic"),
+            AuxPowError::MultipleMagic =>
+                write!(f, "parent coinbase scriptSig contains the merged-mining magic more than once"),
+            AuxPowError::CommitmentMismatch =>
+                write!(f, "blockchain Merkle branch does not commit to this block's hash"),
+            AuxPowError::ChainMerkleSizeMismatch =>
+                write!(f, "merged-mining commitment tree size does not match the Merkle branch height"),
+            AuxPowError::ChainMerkleIndexMismatch =>
+                write!(f, "blockchain Merkle branch index is not this chain's assigned slot"),
+            AuxPowError::CoinbaseBranchMismatch =>
+                write!(f, "coinbase Merkle branch does not hash up to the parent's merkle root"),
+            AuxPowError::ParentPowInvalid => write!(f, "parent header does not meet this chain's target"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AuxPowError {}
+
+/// Keeps tree heights well below 32, where the `1u32 << height` shifts used to validate and
+/// compute a chain's commitment slot would overflow; no real merged-mining setup aggregates
+/// anywhere near `2^30` chains, so this is purely a defensive bound against malformed/adversarial
+/// proofs.
+const MAX_CHAIN_MERKLE_HEIGHT: u32 = 30;
+
+impl AuxPow {
+    /// Validates this proof against `block_hash` (the merge-mined chain's own block hash),
+    /// `chain_id` (this chain's merged-mining identifier), and `target` (the target *this
+    /// chain's* retarget rule computed for the header being checked), per
+    /// [`Header::check_aux_pow`].
+    fn check(&self, block_hash: BlockHash, chain_id: u32, target: Target) -> Result<(), AuxPowError> {
+        // 1. The blockchain Merkle branch must apply to `block_hash` and land on a leaf that
+        // matches the merged-mining commitment the parent coinbase claims to carry.
+        let committed = self.blockchain_branch.apply(block_hash);
+        let script_bytes = self.coinbase_tx.input.first().map(|i| i.script_sig.as_bytes()).unwrap_or(&[]);
+        let magic_pos = find_unique_subslice(script_bytes, &MERGED_MINING_HEADER)?;
+        let commitment_pos = magic_pos + MERGED_MINING_HEADER.len();
+        let commitment = script_bytes
+            .get(commitment_pos..commitment_pos + 32)
+            .ok_or(AuxPowError::CommitmentMismatch)?;
+        if commitment != committed.as_ref() {
+            return Err(AuxPowError::CommitmentMismatch);
+        }
+
+        // The commitment is followed by the merged-mining tree's size (as a power-of-two leaf
+        // count, little-endian u32) and the nonce used to pick each chain's slot within it. Both
+        // must be present: without them there is nothing to check `chain_id`'s slot against, and
+        // a proof for one chain could be replayed against any other sharing the same parent.
+        let size_pos = commitment_pos + 32;
+        let merkle_size = script_bytes
+            .get(size_pos..size_pos + 4)
+            .map(|b| u32::from_le_bytes(b.try_into().expect("4 bytes")))
+            .ok_or(AuxPowError::ChainMerkleSizeMismatch)?;
+        let merge_nonce = script_bytes
+            .get(size_pos + 4..size_pos + 8)
+            .map(|b| u32::from_le_bytes(b.try_into().expect("4 bytes")))
+            .ok_or(AuxPowError::ChainMerkleSizeMismatch)?;
+
+        let height = self.blockchain_branch.hashes.len() as u32;
+        if height > MAX_CHAIN_MERKLE_HEIGHT {
+            return Err(AuxPowError::ChainMerkleSizeMismatch);
+        }
+        if merkle_size != 1u32 << height {
+            return Err(AuxPowError::ChainMerkleSizeMismatch);
+        }
+        if self.blockchain_branch.index != expected_chain_merkle_index(merge_nonce, chain_id, height) {
+            return Err(AuxPowError::ChainMerkleIndexMismatch);
+        }
+
+        // 2. The coinbase transaction must actually be part of the parent block.
+        let coinbase_txid = TxMerkleNode::from_byte_array(self.coinbase_tx.txid().to_byte_array());
+        if self.coinbase_branch.apply(coinbase_txid) != self.parent_header.merkle_root {
+            return Err(AuxPowError::CoinbaseBranchMismatch);
+        }
+
+        // 3. The parent header's hash must meet *this chain's* target. The parent header is
+        // otherwise attacker-supplied data embedded in the block being validated, not an
+        // independently verified parent-chain header, so its own self-reported `bits` cannot be
+        // trusted as the target to check it against - that would let anyone mint a valid AuxPoW
+        // by setting `parent_header.bits` to the loosest value and grinding a trivial nonce.
+        if !target_is_met(self.parent_header.pow_hash(), target) {
+            return Err(AuxPowError::ParentPowInvalid);
+        }
+
+        Ok(())
+    }
+}
+
+/// Finds the one occurrence of `needle` in `haystack`. Real merged-mining implementations
+/// reject a scriptSig containing the merged-mining magic more than once, since a second
+/// occurrence could be used to smuggle an alternate commitment past the check.
+fn find_unique_subslice(haystack: &[u8], needle: &[u8]) -> Result<usize, AuxPowError> {
+    let mut positions = haystack.windows(needle.len()).enumerate().filter(|(_, w)| *w == needle).map(|(i, _)| i);
+    let first = positions.next().ok_or(AuxPowError::MissingMagic)?;
+    if positions.next().is_some() {
+        return Err(AuxPowError::MultipleMagic);
+    }
+    Ok(first)
+}
+
+/// Computes the merged-mining commitment tree slot `chain_id` is assigned to, given the merge
+/// mining nonce embedded in the parent coinbase and the tree's height (`log2` of its leaf count).
+///
+/// This is the standard merged-mining "expected index" formula (as used by e.g. Namecoin): it
+/// pseudo-randomly scatters chains across the tree's leaves, keyed by `nonce`, so that a proof
+/// solved for one `chain_id` cannot simply be replayed for another sharing the same parent block.
+///
+/// # Panics
+///
+/// The `1u32 << height` shift overflows (and panics with overflow checks enabled) if `height` is
+/// 32 or more; callers enforce the tighter [`MAX_CHAIN_MERKLE_HEIGHT`] bound first.
+fn expected_chain_merkle_index(nonce: u32, chain_id: u32, height: u32) -> u32 {
+    let mut rand = nonce;
+    rand = rand.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+    rand = rand.wrapping_add(chain_id);
+    rand = rand.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+    rand % (1u32 << height)
+}
+
+pub(crate) fn target_is_met(hash: BlockHash, target: Target) -> bool {
+    Target::from_le_bytes(hash.to_byte_array()) <= target
+}
+
+impl Encodable for AuxPow {
+    fn consensus_encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, crate::io::Error> {
+        let mut len = self.coinbase_tx.consensus_encode(w)?;
+        len += self.coinbase_branch.consensus_encode(w)?;
+        len += self.blockchain_branch.consensus_encode(w)?;
+        len += self.parent_header.version.consensus_encode(w)?;
+        len += self.parent_header.prev_blockhash.consensus_encode(w)?;
+        len += self.parent_header.merkle_root.consensus_encode(w)?;
+        len += self.parent_header.time.consensus_encode(w)?;
+        len += self.parent_header.bits.consensus_encode(w)?;
+        len += self.parent_header.nonce.consensus_encode(w)?;
+        Ok(len)
+    }
+}
+
+impl Decodable for AuxPow {
+    fn consensus_decode<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, encode::Error> {
+        Ok(AuxPow {
+            coinbase_tx: Decodable::consensus_decode(r)?,
+            coinbase_branch: Decodable::consensus_decode(r)?,
+            blockchain_branch: Decodable::consensus_decode(r)?,
+            parent_header: Box::new(Header {
+                version: Decodable::consensus_decode(r)?,
+                prev_blockhash: Decodable::consensus_decode(r)?,
+                merkle_root: Decodable::consensus_decode(r)?,
+                time: Decodable::consensus_decode(r)?,
+                bits: Decodable::consensus_decode(r)?,
+                nonce: Decodable::consensus_decode(r)?,
+                // A parent header embedded in an AuxPoW proof is never itself merge-mined here.
+                aux_data: None,
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::blockdata::locktime::absolute;
+    use crate::blockdata::script::ScriptBuf;
+    use crate::blockdata::transaction::{self, OutPoint, Sequence, TxIn, TxOut};
+    use crate::blockdata::witness::Witness;
+    use crate::consensus::Params;
+    use crate::network::Network;
+    use crate::Amount;
+
+    // Builds an `AuxPow` whose parent coinbase commits to `block_hash` at `blockchain_branch`'s
+    // slot, using `merge_nonce` as the merged-mining nonce, so `chain_id` is the only free
+    // variable callers need to vary between a genuine and a replayed proof.
+    fn make_aux_pow(block_hash: BlockHash, blockchain_branch: MerkleBranch<BlockHash>, merge_nonce: u32) -> AuxPow {
+        let committed = blockchain_branch.apply(block_hash);
+
+        let mut script_bytes = Vec::new();
+        script_bytes.extend_from_slice(&MERGED_MINING_HEADER);
+        script_bytes.extend_from_slice(committed.as_ref());
+        let merkle_size = 1u32 << blockchain_branch.hashes.len();
+        script_bytes.extend_from_slice(&merkle_size.to_le_bytes());
+        script_bytes.extend_from_slice(&merge_nonce.to_le_bytes());
+
+        let coinbase_tx = Transaction {
+            version: transaction::Version::ONE,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::from_bytes(script_bytes),
+                sequence: Sequence::MAX,
+                witness: Witness::default(),
+            }],
+            output: vec![TxOut { value: Amount::from_sat(0), script_pubkey: ScriptBuf::new() }],
+        };
+        let coinbase_txid = TxMerkleNode::from_byte_array(coinbase_tx.txid().to_byte_array());
+
+        // A parent header whose `bits` saturate to `Target::MAX` (via `Target::from_compact`'s
+        // mantissa-overflow path), the loosest value it could self-report. `AuxPow::check` never
+        // uses these `bits`: it checks the parent hash against the target it is given, which the
+        // tests set to `Target::MAX` wherever the check must pass, so they stay deterministic.
+        let parent_header = Header {
+            version: Version::ONE,
+            prev_blockhash: Hash::all_zeros(),
+            merkle_root: coinbase_txid,
+            time: 0,
+            bits: CompactTarget::from_consensus(0xff000001),
+            nonce: 0,
+            aux_data: None,
+        };
+
+        AuxPow {
+            coinbase_tx,
+            coinbase_branch: MerkleBranch { hashes: vec![], index: 0 },
+            blockchain_branch,
+            parent_header: Box::new(parent_header),
+        }
+    }
+
+    fn test_params() -> Params {
+        let mut params = Params::new(Network::Regtest);
+        params.pow_limit = Target::MAX;
+        params
+    }
+
+    #[test]
+    fn check_aux_pow_accepts_matching_chain_id() {
+        let block_hash = BlockHash::from_byte_array([7; 32]);
+        // height 4 (16 slots); with nonce 7, `chain_id` 2 is assigned index 7.
+        let branch = MerkleBranch {
+            hashes: vec![
+                BlockHash::from_byte_array([1; 32]),
+                BlockHash::from_byte_array([2; 32]),
+                BlockHash::from_byte_array([3; 32]),
+                BlockHash::from_byte_array([4; 32]),
+            ],
+            index: 7,
+        };
+        let aux_pow = make_aux_pow(block_hash, branch, 7);
+
+        assert_eq!(aux_pow.check(block_hash, 2, Target::MAX), Ok(()));
+    }
+
+    #[test]
+    fn check_aux_pow_rejects_wrong_chain_id() {
+        let block_hash = BlockHash::from_byte_array([7; 32]);
+        // Same proof as above, assigned to chain_id 2's slot (index 7); chain_id 3 is assigned
+        // index 4 for the same nonce, so this proof must not be credited to it.
+        let branch = MerkleBranch {
+            hashes: vec![
+                BlockHash::from_byte_array([1; 32]),
+                BlockHash::from_byte_array([2; 32]),
+                BlockHash::from_byte_array([3; 32]),
+                BlockHash::from_byte_array([4; 32]),
+            ],
+            index: 7,
+        };
+        let aux_pow = make_aux_pow(block_hash, branch, 7);
+
+        assert_eq!(aux_pow.check(block_hash, 3, Target::MAX), Err(AuxPowError::ChainMerkleIndexMismatch));
+    }
+
+    #[test]
+    fn check_aux_pow_rejects_wrong_block_hash() {
+        let block_hash = BlockHash::from_byte_array([7; 32]);
+        let branch = MerkleBranch { hashes: vec![], index: 0 };
+        let aux_pow = make_aux_pow(block_hash, branch, 0);
+
+        let other_hash = BlockHash::from_byte_array([9; 32]);
+        assert_eq!(aux_pow.check(other_hash, 0, Target::MAX), Err(AuxPowError::CommitmentMismatch));
+    }
+
+    #[test]
+    fn check_aux_pow_rejects_parent_not_meeting_child_target() {
+        let block_hash = BlockHash::from_byte_array([7; 32]);
+        let branch = MerkleBranch { hashes: vec![], index: 0 };
+        let aux_pow = make_aux_pow(block_hash, branch, 0);
+
+        // The parent header's own (attacker-controlled) `bits` happily saturate to `Target::MAX`
+        // (see `make_aux_pow`), but this chain's actual target is far stricter; the parent hash
+        // must meet *that* target, not whatever the parent header self-reports.
+        let strict_target = Target::from_compact(CompactTarget::from_consensus(0x01000001)); // == 0
+        assert_eq!(aux_pow.check(block_hash, 0, strict_target), Err(AuxPowError::ParentPowInvalid));
+    }
+
+    #[test]
+    fn check_aux_pow_rejects_duplicate_magic() {
+        let block_hash = BlockHash::from_byte_array([7; 32]);
+        let branch = MerkleBranch { hashes: vec![], index: 0 };
+        let mut aux_pow = make_aux_pow(block_hash, branch, 0);
+
+        // Splice a second copy of the magic into the scriptSig ahead of the real commitment.
+        let mut script_bytes = aux_pow.coinbase_tx.input[0].script_sig.as_bytes().to_vec();
+        let mut spliced = MERGED_MINING_HEADER.to_vec();
+        spliced.append(&mut script_bytes);
+        aux_pow.coinbase_tx.input[0].script_sig = ScriptBuf::from_bytes(spliced);
+
+        assert_eq!(aux_pow.check(block_hash, 0, Target::MAX), Err(AuxPowError::MultipleMagic));
+    }
+
+    #[test]
+    fn check_aux_pow_rejects_oversized_chain_merkle_height() {
+        let block_hash = BlockHash::from_byte_array([7; 32]);
+        let branch =
+            MerkleBranch { hashes: vec![BlockHash::from_byte_array([1; 32]); 31], index: 0 };
+        let aux_pow = make_aux_pow(block_hash, branch, 0);
+
+        assert_eq!(aux_pow.check(block_hash, 0, Target::MAX), Err(AuxPowError::ChainMerkleSizeMismatch));
+    }
+
+    #[test]
+    fn check_aux_pow_gated_by_start_height() {
+        let header = Header {
+            version: Version::ONE,
+            prev_blockhash: Hash::all_zeros(),
+            merkle_root: Hash::all_zeros(),
+            time: 0,
+            bits: CompactTarget::from_consensus(0x1e0ffff0),
+            nonce: 0,
+            aux_data: None,
+        };
+
+        let mut params = test_params();
+        params.aux_pow_start_height = Some(100);
+
+        // Below the activation height, a missing `aux_data` is fine.
+        assert_eq!(header.check_aux_pow(50, 0, &params), Ok(()));
+        // At or above it, `aux_data` is required.
+        assert_eq!(header.check_aux_pow(100, 0, &params), Err(AuxPowError::Missing));
+    }
+}
@@ -0,0 +1,166 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! BIP9 versionbits soft-fork deployments.
+//!
+//! This module describes soft-fork deployments signalled through the block header's version
+//! field (BIP9) and implements the state machine used to decide, for a given retarget period,
+//! whether a deployment is not yet proposed, signalling, locked in, active, or has failed.
+//!
+
+use crate::blockdata::block::Version;
+use crate::consensus::Params;
+
+/// `nVersion` top bits that must be set for versionbits signalling to be recognised (BIP9).
+const VERSIONBITS_TOP_MASK: i32 = 0xe000_0000u32 as i32;
+/// Value the top bits must have for versionbits signalling to be recognised (BIP9).
+const VERSIONBITS_TOP_BITS: i32 = 0x2000_0000;
+
+/// Description of a single BIP9 soft-fork deployment.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Deployment {
+    /// The versionbits bit (0..=28) this deployment signals on.
+    pub bit: u8,
+    /// Median-time-past at or after which signalling for this deployment begins.
+    pub start_time: u32,
+    /// Median-time-past at or after which an un-locked-in deployment is considered failed.
+    pub timeout: u32,
+}
+
+/// The state of a BIP9 deployment as of a particular retarget period, per the standard BIP9
+/// state machine.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BIP9State {
+    /// The deployment has not started signalling yet (MTP is before `start_time`).
+    Defined,
+    /// The deployment is signalling but has not yet reached the activation threshold.
+    Started,
+    /// The activation threshold was reached in a previous period; one more full period of
+    /// confirmation is required before the deployment becomes active.
+    LockedIn,
+    /// The deployment's rules are enforced.
+    Active,
+    /// Signalling stopped (MTP reached `timeout`) before the threshold was reached.
+    Failed,
+}
+
+impl Deployment {
+    /// Computes the next state of this deployment given the previous state, the median-time-past
+    /// of the block starting the period just completed, and how many blocks in that just-completed
+    /// `miner_confirmation_window` signalled this deployment's bit.
+    ///
+    /// `signalling_count` must only count blocks within the most recent `params.miner_confirmation_window`
+    /// blocks, i.e. the retarget period that just elapsed.
+    pub fn next_state(
+        &self,
+        previous_state: BIP9State,
+        median_time_past: u32,
+        signalling_count: u32,
+        params: &Params,
+    ) -> BIP9State {
+        match previous_state {
+            BIP9State::Defined =>
+                if median_time_past >= self.timeout {
+                    BIP9State::Failed
+                } else if median_time_past >= self.start_time {
+                    BIP9State::Started
+                } else {
+                    BIP9State::Defined
+                },
+            BIP9State::Started => {
+                if signalling_count >= params.rule_change_activation_threshold {
+                    BIP9State::LockedIn
+                } else if median_time_past >= self.timeout {
+                    BIP9State::Failed
+                } else {
+                    BIP9State::Started
+                }
+            }
+            // `LockedIn` always advances to `Active` after exactly one more full confirmation
+            // window has elapsed, regardless of further signalling.
+            BIP9State::LockedIn => BIP9State::Active,
+            BIP9State::Active => BIP9State::Active,
+            BIP9State::Failed => BIP9State::Failed,
+        }
+    }
+}
+
+impl Version {
+    /// Returns whether this version signals support for `deployment`'s bit via BIP9 versionbits.
+    pub fn signals_deployment(&self, deployment: Deployment) -> bool {
+        let v = self.to_consensus();
+        (v & VERSIONBITS_TOP_MASK) == VERSIONBITS_TOP_BITS && (v & (1 << deployment.bit)) != 0
+    }
+
+    /// Returns a copy of this version with `deployment`'s signalling bit set (and the BIP9 top
+    /// bits set appropriately), for use by miners opting in to a soft fork.
+    pub fn with_deployment_signalled(self, deployment: Deployment) -> Version {
+        let v = self.to_consensus();
+        let v = (v & !VERSIONBITS_TOP_MASK) | VERSIONBITS_TOP_BITS | (1 << deployment.bit);
+        Version::from_consensus(v)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::network::Network;
+
+    const DEPLOYMENT: Deployment = Deployment { bit: 1, start_time: 1_000, timeout: 2_000 };
+
+    #[test]
+    fn signals_deployment_checks_top_bits_and_bit_position() {
+        let signalling = Version::ONE.with_deployment_signalled(DEPLOYMENT);
+        assert!(signalling.signals_deployment(DEPLOYMENT));
+
+        // A version with the right bit set but without the BIP9 top bits does not count.
+        let no_top_bits = Version::from_consensus(1 << DEPLOYMENT.bit);
+        assert!(!no_top_bits.signals_deployment(DEPLOYMENT));
+
+        // The BIP9 top bits with a different bit set does not count either.
+        let other_bit = Deployment { bit: 2, ..DEPLOYMENT };
+        assert!(!signalling.signals_deployment(other_bit));
+    }
+
+    #[test]
+    fn next_state_walks_defined_through_active() {
+        let params = Params::new(Network::Regtest);
+
+        // Before `start_time`: stays `Defined`.
+        let state = DEPLOYMENT.next_state(BIP9State::Defined, 500, 0, &params);
+        assert_eq!(state, BIP9State::Defined);
+
+        // At or after `start_time`, signalling begins.
+        let state = DEPLOYMENT.next_state(BIP9State::Defined, 1_000, 0, &params);
+        assert_eq!(state, BIP9State::Started);
+
+        // Not enough signalling yet: stays `Started`.
+        let state =
+            DEPLOYMENT.next_state(BIP9State::Started, 1_500, params.rule_change_activation_threshold - 1, &params);
+        assert_eq!(state, BIP9State::Started);
+
+        // Threshold reached: locks in regardless of the timeout having passed.
+        let state =
+            DEPLOYMENT.next_state(BIP9State::Started, 2_000, params.rule_change_activation_threshold, &params);
+        assert_eq!(state, BIP9State::LockedIn);
+
+        // `LockedIn` always advances to `Active` after one more period.
+        let state = DEPLOYMENT.next_state(BIP9State::LockedIn, 1_500, 0, &params);
+        assert_eq!(state, BIP9State::Active);
+
+        // `Active` and `Failed` are terminal.
+        assert_eq!(DEPLOYMENT.next_state(BIP9State::Active, 9_999, 0, &params), BIP9State::Active);
+        assert_eq!(DEPLOYMENT.next_state(BIP9State::Failed, 9_999, 0, &params), BIP9State::Failed);
+    }
+
+    #[test]
+    fn next_state_fails_on_timeout_without_lock_in() {
+        let params = Params::new(Network::Regtest);
+
+        let state = DEPLOYMENT.next_state(BIP9State::Defined, 2_000, 0, &params);
+        assert_eq!(state, BIP9State::Failed);
+
+        let state =
+            DEPLOYMENT.next_state(BIP9State::Started, 2_000, params.rule_change_activation_threshold - 1, &params);
+        assert_eq!(state, BIP9State::Failed);
+    }
+}
@@ -6,8 +6,10 @@
 //! chains (such as mainnet, testnet).
 //!
 
+use crate::consensus::deployments::Deployment;
 use crate::network::Network;
-use crate::pow::Target;
+use crate::pow::{CompactTarget, Target, Work};
+use crate::BlockHash;
 
 /// Parameters that influence chain consensus.
 #[non_exhaustive]
@@ -46,6 +48,33 @@ pub struct Params {
     pub allow_min_difficulty_blocks: bool,
     /// Determines whether retargeting is disabled for this network or not.
     pub no_pow_retargeting: bool,
+    /// Number of blocks averaged to compute the next difficulty target (DigiShield-style
+    /// retargeting, evaluated every block rather than every `difficulty_adjustment_interval`).
+    pub pow_averaging_window: u32,
+    /// Maximum percentage the averaging-window timespan is allowed to grow by, which in turn
+    /// bounds how much easier the next target may become in a single retarget.
+    pub pow_max_adjust_down: u32,
+    /// Maximum percentage the averaging-window timespan is allowed to shrink by, which in turn
+    /// bounds how much harder the next target may become in a single retarget.
+    pub pow_max_adjust_up: u32,
+    /// BIP9 soft-fork deployments tracked on this network.
+    pub deployments: &'static [Deployment],
+    /// The minimum amount of chain work a node requires a chain to have accumulated before it
+    /// will consider that chain as a candidate for the best chain.
+    pub minimum_chain_work: Work,
+    /// The hash of a block below which script verification may be skipped during initial block
+    /// download, since the block is known (by virtue of shipping in this release) to be on the
+    /// most-work valid chain. `None` if no such hash is trusted for this network.
+    pub default_assume_valid: Option<BlockHash>,
+    /// Height/hash pairs of blocks that are known to be part of the valid, most-work chain. Used
+    /// to cheaply reject alternate chains that fork below the highest checkpoint.
+    pub checkpoints: &'static [(u32, BlockHash)],
+    /// Block height at which AuxPoW (merged-mining) validation rules activate. Blocks below this
+    /// height keep validating with the plain PoW path and any `aux_data` they carry is not checked; `None`
+    /// means this network never activates AuxPoW. See [`block::Header::check_aux_pow`].
+    ///
+    /// [`block::Header::check_aux_pow`]: crate::blockdata::block::Header::check_aux_pow
+    pub aux_pow_start_height: Option<u32>,
 }
 
 impl Params {
@@ -66,6 +95,14 @@ impl Params {
                 pow_target_timespan: 24 * 60 * 60, // 1 day from nPowTargetTimespan
                 allow_min_difficulty_blocks: false,
                 no_pow_retargeting: false,
+                pow_averaging_window: 17,
+                pow_max_adjust_down: 32,
+                pow_max_adjust_up: 16,
+                deployments: &[],
+                minimum_chain_work: Work::ZERO,
+                default_assume_valid: None,
+                checkpoints: &[],
+                aux_pow_start_height: None,
             },
             Network::Testnet => Params {
                 network: Network::Testnet,
@@ -80,6 +117,14 @@ impl Params {
                 pow_target_timespan: 4 * 60 * 60, // 4 hours from consensus
                 allow_min_difficulty_blocks: true, // from fPowAllowMinDifficultyBlocks
                 no_pow_retargeting: false,
+                pow_averaging_window: 17,
+                pow_max_adjust_down: 32,
+                pow_max_adjust_up: 16,
+                deployments: &[],
+                minimum_chain_work: Work::ZERO,
+                default_assume_valid: None,
+                checkpoints: &[],
+                aux_pow_start_height: None,
             },
             Network::Signet => Params {
                 network: Network::Signet,
@@ -94,6 +139,14 @@ impl Params {
                 pow_target_timespan: 4 * 60 * 60, // 4 hours
                 allow_min_difficulty_blocks: true,
                 no_pow_retargeting: false,
+                pow_averaging_window: 17,
+                pow_max_adjust_down: 32,
+                pow_max_adjust_up: 16,
+                deployments: &[],
+                minimum_chain_work: Work::ZERO,
+                default_assume_valid: None,
+                checkpoints: &[],
+                aux_pow_start_height: None,
             },
             Network::Regtest => Params {
                 network: Network::Regtest,
@@ -108,6 +161,14 @@ impl Params {
                 pow_target_timespan: 4 * 60 * 60, // 4 hours timespan
                 allow_min_difficulty_blocks: true,
                 no_pow_retargeting: false,
+                pow_averaging_window: 17,
+                pow_max_adjust_down: 32,
+                pow_max_adjust_up: 16,
+                deployments: &[],
+                minimum_chain_work: Work::ZERO,
+                default_assume_valid: None,
+                checkpoints: &[],
+                aux_pow_start_height: None,
             },
         }
     }
@@ -116,4 +177,130 @@ impl Params {
     pub fn difficulty_adjustment_interval(&self) -> u64 {
         self.pow_target_timespan / self.pow_target_spacing
     }
+
+    /// Computes the next target using DigiShield-style averaging-window retargeting.
+    ///
+    /// `last_targets` and `window_timestamps` must each hold at least `pow_averaging_window`
+    /// entries for the blocks immediately preceding the one being produced, oldest first;
+    /// `window_timestamps` should be median-time-past values where available, to resist
+    /// timestamp manipulation. `previous_bits` is the immediately preceding block's `bits`, and
+    /// `current_time` is the timestamp of the block whose target is being computed.
+    ///
+    /// Returns `self.pow_limit`'s compact form if fewer than `pow_averaging_window` blocks are
+    /// available, echoes `previous_bits` when `no_pow_retargeting` is set, and returns
+    /// `self.pow_limit`'s compact form when `allow_min_difficulty_blocks` is set and the gap
+    /// since the last block exceeds twice the target spacing.
+    pub fn next_work_required(
+        &self,
+        previous_bits: CompactTarget,
+        current_time: u32,
+        last_targets: &[Target],
+        window_timestamps: &[u32],
+    ) -> CompactTarget {
+        if self.no_pow_retargeting {
+            return previous_bits;
+        }
+
+        let window = self.pow_averaging_window as usize;
+        if last_targets.len() < window || window_timestamps.len() < window {
+            return self.pow_limit.to_compact_lossy();
+        }
+
+        if self.allow_min_difficulty_blocks {
+            let last_timestamp = window_timestamps[window_timestamps.len() - 1];
+            if current_time.saturating_sub(last_timestamp) > 2 * self.pow_target_spacing as u32 {
+                return self.pow_limit.to_compact_lossy();
+            }
+        }
+
+        let averaging_timespan = window as u64 * self.pow_target_spacing;
+        let min_timespan = averaging_timespan * (100 - self.pow_max_adjust_up) as u64 / 100;
+        let max_timespan = averaging_timespan * (100 + self.pow_max_adjust_down) as u64 / 100;
+
+        let first_timestamp = window_timestamps[window_timestamps.len() - window];
+        let last_timestamp = window_timestamps[window_timestamps.len() - 1];
+        let actual_timespan =
+            (last_timestamp.saturating_sub(first_timestamp) as u64).clamp(min_timespan, max_timespan);
+
+        let targets = &last_targets[last_targets.len() - window..];
+        let avg: Target = targets.iter().copied().sum::<Target>() / window as u64;
+
+        let new_target = (avg / averaging_timespan * actual_timespan).clamp_max(self.pow_limit);
+        new_target.to_compact_lossy()
+    }
+
+    /// Returns `false` only if a checkpoint exists at `height` and its hash disagrees with
+    /// `hash`; any other height (including one with no checkpoint at all) is considered
+    /// consistent.
+    pub fn is_checkpoint_consistent(&self, height: u32, hash: &BlockHash) -> bool {
+        match self.checkpoints.iter().find(|(checkpoint_height, _)| *checkpoint_height == height) {
+            Some((_, checkpoint_hash)) => checkpoint_hash == hash,
+            None => true,
+        }
+    }
+
+    /// Returns whether a block at `height` must validate via the AuxPoW path (as opposed to the
+    /// plain PoW path) on this network.
+    pub fn requires_aux_pow(&self, height: u32) -> bool {
+        self.aux_pow_start_height.is_some_and(|start| height >= start)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn next_work_required_averages_and_clamps_timespan() {
+        let mut params = Params::new(Network::Regtest);
+        params.pow_averaging_window = 3;
+        params.pow_target_spacing = 60;
+        params.pow_max_adjust_up = 16;
+        params.pow_max_adjust_down = 32;
+        params.allow_min_difficulty_blocks = false;
+        params.pow_limit = Target::MAX;
+
+        let target = Target::from_compact(CompactTarget::from_consensus(0x030003e8)); // 1000
+        let last_targets = [target, target, target];
+        // Spaced tighter than the 3*60s window, so the actual timespan gets clamped up to
+        // `min_timespan` (151s) before being applied.
+        let window_timestamps = [0, 60, 120];
+
+        let bits = params.next_work_required(
+            CompactTarget::from_consensus(0x030003e8),
+            120,
+            &last_targets,
+            &window_timestamps,
+        );
+        let expected = Target::from_compact(CompactTarget::from_consensus(0x030002f3)); // 755
+        assert_eq!(Target::from_compact(bits), expected);
+    }
+
+    #[test]
+    fn next_work_required_echoes_previous_bits_when_retargeting_disabled() {
+        let mut params = Params::new(Network::Regtest);
+        params.no_pow_retargeting = true;
+        let previous_bits = CompactTarget::from_consensus(0x1e0ffff0);
+        let bits = params.next_work_required(previous_bits, 0, &[], &[]);
+        assert_eq!(bits, previous_bits);
+    }
+
+    #[test]
+    fn next_work_required_falls_back_to_pow_limit_with_too_short_a_window() {
+        let params = Params::new(Network::Regtest);
+        let bits = params.next_work_required(CompactTarget::from_consensus(0x1e0ffff0), 0, &[], &[]);
+        assert_eq!(bits, params.pow_limit.to_compact_lossy());
+    }
+
+    #[test]
+    fn is_checkpoint_consistent_checks_only_known_heights() {
+        let mut params = Params::new(Network::Bitcoin);
+        let hash = BlockHash::from_byte_array([1; 32]);
+        let other_hash = BlockHash::from_byte_array([2; 32]);
+        params.checkpoints = &[(100, BlockHash::from_byte_array([1; 32]))];
+
+        assert!(params.is_checkpoint_consistent(100, &hash));
+        assert!(!params.is_checkpoint_consistent(100, &other_hash));
+        assert!(params.is_checkpoint_consistent(200, &other_hash));
+    }
 }
@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! Contextual proof-of-work validation for block headers.
+//!
+//! Checking that a header's hash meets its own `bits` is not enough on its own: a header also
+//! has to carry the *correct* `bits` for its place in the chain, as determined by this network's
+//! retarget rule, and - past `params.aux_pow_start_height` - its proof-of-work is the embedded
+//! [`AuxPow`](block::AuxPow)'s parent header rather than the header's own hash. This module ties [`Header`],
+//! [`Params`], the averaging-window retarget, and AuxPoW together into one contextual check.
+//!
+
+use core::fmt;
+
+use crate::blockdata::block::{self, AuxPowError, Header};
+use crate::consensus::Params;
+use crate::pow::{CompactTarget, Target};
+
+/// Errors returned when a header's proof-of-work does not meet what this chain's consensus rules
+/// require of it at its height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowError {
+    /// The header's `bits` do not match the value the retarget rule computed for this height.
+    BadDifficultyBits {
+        /// What the retarget rule says `bits` should have been.
+        expected: CompactTarget,
+        /// What the header actually carries.
+        got: CompactTarget,
+    },
+    /// The header's proof-of-work hash is numerically above the target its own `bits` decode to.
+    HashAboveTarget,
+    /// The header's target is looser (i.e. easier) than `pow_limit` allows.
+    TargetAbovePowLimit,
+    /// Past `aux_pow_start_height`, the header's embedded [`AuxPow`] failed to validate.
+    ///
+    /// [`AuxPow`]: crate::blockdata::block::AuxPow
+    AuxPow(AuxPowError),
+}
+
+impl fmt::Display for PowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PowError::BadDifficultyBits { expected, got } =>
+                write!(f, "header bits {:?} do not match the expected retarget {:?}", got, expected),
+            PowError::HashAboveTarget => write!(f, "header hash is above its own target"),
+            PowError::TargetAbovePowLimit => write!(f, "header target is above this network's pow_limit"),
+            PowError::AuxPow(e) => write!(f, "AuxPoW validation failed: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PowError {}
+
+/// Checks that `header` satisfies `expected_bits`, that the resulting target does not exceed
+/// `params.pow_limit`, and that the proof-of-work itself is valid for `height` - via the plain PoW
+/// path below `params.aux_pow_start_height`, or via `header`'s embedded [`AuxPow`](block::AuxPow)
+/// at or above it.
+///
+/// This does not recompute what `expected_bits` should be; use [`Params::check_header_pow`] when
+/// the caller has the retarget window on hand and wants that computed too.
+pub fn check_proof_of_work(
+    header: &Header,
+    expected_bits: CompactTarget,
+    height: u32,
+    chain_id: u32,
+    params: &Params,
+) -> Result<(), PowError> {
+    if header.bits != expected_bits {
+        return Err(PowError::BadDifficultyBits { expected: expected_bits, got: header.bits });
+    }
+
+    let target = header.target();
+    if target > params.pow_limit {
+        return Err(PowError::TargetAbovePowLimit);
+    }
+
+    if params.requires_aux_pow(height) {
+        header.check_aux_pow(height, chain_id, params).map_err(PowError::AuxPow)?;
+    } else if !block::target_is_met(header.pow_hash(), target) {
+        return Err(PowError::HashAboveTarget);
+    }
+
+    Ok(())
+}
+
+impl Params {
+    /// Recomputes the `bits` this network's averaging-window retarget rule requires of `header`
+    /// and checks `header` against it, per [`check_proof_of_work`].
+    ///
+    /// See [`Params::next_work_required`] for the meaning of `previous_bits`, `last_targets`, and
+    /// `window_timestamps`; see [`check_proof_of_work`] for `height` and `chain_id`.
+    pub fn check_header_pow(
+        &self,
+        header: &Header,
+        height: u32,
+        chain_id: u32,
+        previous_bits: CompactTarget,
+        last_targets: &[Target],
+        window_timestamps: &[u32],
+    ) -> Result<(), PowError> {
+        let expected =
+            self.next_work_required(previous_bits, header.time, last_targets, window_timestamps);
+        check_proof_of_work(header, expected, height, chain_id, self)
+    }
+}